@@ -3,61 +3,174 @@
 use std::{
     alloc,
     borrow::{Borrow, Cow},
+    cmp::Ordering,
     fmt, hash,
     mem::{size_of, ManuallyDrop},
+    num::NonZeroU8,
     ptr::{self, NonNull},
     slice, str,
+    sync::{
+        atomic::{self, AtomicUsize},
+        Arc,
+    },
 };
 
 /// A very very small owned string type.
 ///
 /// This type is like a `Box<str>` and is similarly two `usize`s large. It can only fit strings
-/// with a byte length smaller than 256. On 64-bit machines this type stores up to 15 bytes inline
-/// (7 bytes on 32-bit machines). One byte is used to store the length. For strings short enough
-/// to be stored inline, the remaining 15 (or 7) bytes store the content inline. Otherwise the
-/// second `usize` of memory is a thin pointer to the string content.
+/// with a byte length of at most 63. On 64-bit machines this type stores up to 15 bytes inline
+/// (7 bytes on 32-bit machines). One byte is used to store the length and the heap tag (see
+/// [`HeapTag`]). For strings short enough to be stored inline, the remaining 15 (or 7) bytes store
+/// the content inline. Otherwise the second `usize` of memory is a thin pointer to the string
+/// content (directly to the bytes for [`HeapTag::Boxed`]/[`HeapTag::Shared`], or to an
+/// [`ArenaView`] for [`HeapTag::Arena`]).
 ///
-/// Unlike `Box<str>` this type is not null-pointer optimized.
+/// `TinyBoxedStr` has a niche in its length byte: `Option<TinyBoxedStr>` is the same size as
+/// `TinyBoxedStr`. This matters for large collections of optional short strings (completion
+/// items, diagnostic codes).
+pub type TinyBoxedStr = TinyStr<{ size_of::<usize>() - size_of::<u8>() }>;
+
+/// The generic form of [`TinyBoxedStr`], parameterized over how many bytes of a string are stored
+/// in the leading inline `prefix` before the trailing pointer-sized word.
+///
+/// `N` is the *prefix* length, not the whole inline capacity: a `TinyStr<N>` can inline strings up
+/// to `N + size_of::<usize>()` bytes (`TinyBoxedStr`'s default `N` reproduces today's 15-or-7-byte
+/// threshold). Raising `N` trades a bigger stack footprint for heap allocations further out on the
+/// length distribution; callers that know their strings usually run longer than the default (e.g.
+/// per-grapheme or per-key-event strings) can pick a roomier `N` instead of eating a heap
+/// allocation on every value. Choosing an `N` with `(N + 1) % size_of::<usize>() == 0` avoids
+/// padding before the trailing word so the struct stays exactly `N + 1 + size_of::<usize>()` bytes.
 #[repr(C)]
-pub struct TinyBoxedStr {
-    len: u8,
-    prefix: [u8; Self::PREFIX_LEN],
-    trailing: TinyBoxedStrTrailing,
+pub struct TinyStr<const N: usize> {
+    // The low two bits are the `HeapTag` (meaningless for inline strings); the next bits are the
+    // string's length; the whole byte is stored off-by-one (see `Self::make_len_tag`) so that
+    // `0` is an illegal value no real string ever produces, giving the compiler a niche to store
+    // `None` in.
+    len_tag: NonZeroU8,
+    prefix: [u8; N],
+    trailing: TinyStrTrailing,
 }
 
+// The pointer-or-suffix word is always one `usize` regardless of `N`, so it does not need to be
+// generic.
 #[repr(C)]
-union TinyBoxedStrTrailing {
-    suffix: [u8; TinyBoxedStr::SUFFIX_LEN],
+union TinyStrTrailing {
+    suffix: [u8; SUFFIX_LEN],
+    // For `Boxed` and `Shared` strings this points directly at the string's bytes, so
+    // `as_bytes`/`as_str` do not need an extra indirection to read them. `Shared` strings
+    // additionally have a `SharedHeader` living `SharedHeader::SIZE` bytes before this pointer.
+    // For `Arena` strings this instead points at a heap-allocated `ArenaView`.
     ptr: ManuallyDrop<NonNull<u8>>,
 }
 
-impl TinyBoxedStr {
-    // 1 usize minus the byte to store the length.
-    const PREFIX_LEN: usize = size_of::<usize>() - size_of::<u8>();
-    // The other `usize` is a pointer or the end parts of an inline string.
-    const SUFFIX_LEN: usize = size_of::<usize>();
-    // ... for a grand total of 15 bytes for 64-bit machines or 7 for 32-bit.
-    const INLINE_LEN: u8 = (Self::PREFIX_LEN + Self::SUFFIX_LEN) as u8;
+// The other `usize` is a pointer or the trailing part of an inline string.
+const SUFFIX_LEN: usize = size_of::<usize>();
+
+/// The representation used by a non-inline (heap-allocated) `TinyStr`.
+///
+/// This is packed into the low two bits of `TinyStr`'s length byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum HeapTag {
+    /// `trailing.ptr` points at a uniquely-owned allocation with the layout returned by
+    /// `TinyStr::layout`. Dropping deallocates it immediately.
+    Boxed = 0,
+    /// `trailing.ptr` points at the bytes following a `SharedHeader`. Cloning bumps the header's
+    /// reference count instead of copying the bytes; dropping decrements it and only deallocates
+    /// once the count reaches zero.
+    Shared = 1,
+    /// `trailing.ptr` points at a heap-allocated `ArenaView`, which in turn borrows a range of a
+    /// shared `Arc<[u8]>` arena. Cloning duplicates the (small, fixed-size) `ArenaView` and bumps
+    /// the arena's own `Arc` refcount; dropping drops the `ArenaView`, which drops its `Arc`
+    /// handle. See [`TinyStr::parse_many`].
+    Arena = 2,
+}
+
+/// The atomic reference count prefixed onto a `Shared` string's allocation.
+#[repr(C)]
+struct SharedHeader {
+    count: AtomicUsize,
+}
+
+impl SharedHeader {
+    const SIZE: usize = size_of::<Self>();
+}
+
+/// The out-of-line control block an `Arena`-tagged `TinyStr` points to: which arena it borrows
+/// from, and where in that arena its bytes start (the length is already stored in the `TinyStr`
+/// itself).
+struct ArenaView {
+    arena: Arc<[u8]>,
+    offset: usize,
+}
+
+impl<const N: usize> TinyStr<N> {
+    // ... for a grand total of `N + SUFFIX_LEN` bytes inline.
+    const INLINE_LEN: u8 = (N + SUFFIX_LEN) as u8;
+
+    // Two bits of the length byte are stolen for the `HeapTag` (three variants: `Boxed`,
+    // `Shared`, `Arena`).
+    const TAG_BITS: u32 = 2;
+    const TAG_MASK: u8 = (1 << Self::TAG_BITS) - 1;
+
+    // `HeapTag` only has three variants, so the all-ones tag (`0b11`) is never produced by
+    // `make_len_tag`. That leaves `raw == u8::MAX` (the only case where `raw + 1` would overflow)
+    // unreachable without having to steal a whole length value for it, so every length that fits
+    // in the remaining bits is usable.
+    pub const MAX_LEN: usize = (u8::MAX >> Self::TAG_BITS) as usize;
 
-    pub const MAX_LEN: usize = u8::MAX as usize;
+    #[inline]
+    fn make_len_tag(len: u8, tag: HeapTag) -> NonZeroU8 {
+        let raw = (len << Self::TAG_BITS) | tag as u8;
+        // SAFETY: `len <= MAX_LEN` and `tag as u8 <= HeapTag::Arena as u8` (the largest defined
+        // variant), so `raw <= u8::MAX - 1` and `raw + 1` never overflows or equals zero.
+        unsafe { NonZeroU8::new_unchecked(raw + 1) }
+    }
+
+    #[inline]
+    fn raw_len_tag(&self) -> u8 {
+        self.len_tag.get() - 1
+    }
+
+    #[inline]
+    fn raw_len(&self) -> u8 {
+        self.raw_len_tag() >> Self::TAG_BITS
+    }
+
+    /// The heap representation of a non-inline string.
+    ///
+    /// SAFETY: only meaningful when `self.raw_len() > Self::INLINE_LEN`.
+    #[inline]
+    fn heap_tag(&self) -> HeapTag {
+        debug_assert!(self.raw_len() > Self::INLINE_LEN);
+        match self.raw_len_tag() & Self::TAG_MASK {
+            tag if tag == HeapTag::Shared as u8 => HeapTag::Shared,
+            tag if tag == HeapTag::Arena as u8 => HeapTag::Arena,
+            _ => HeapTag::Boxed,
+        }
+    }
 
     #[inline]
     pub fn len(&self) -> usize {
-        self.len as usize
+        self.raw_len() as usize
     }
 
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.len == 0
+        self.raw_len() == 0
     }
 
     pub fn as_bytes(&self) -> &[u8] {
-        let ptr = if self.len <= Self::INLINE_LEN {
+        if self.raw_len() <= Self::INLINE_LEN {
             let ptr = ptr::from_ref(self);
-            unsafe { ptr::addr_of!((*ptr).prefix) }.cast()
-        } else {
-            unsafe { self.trailing.ptr }.as_ptr()
-        };
+            let ptr = unsafe { ptr::addr_of!((*ptr).prefix) }.cast();
+            return unsafe { slice::from_raw_parts(ptr, self.len()) };
+        }
+        if self.heap_tag() == HeapTag::Arena {
+            let view = unsafe { &*self.trailing.ptr.as_ptr().cast::<ArenaView>() };
+            return &view.arena[view.offset..view.offset + self.len()];
+        }
+        let ptr = unsafe { self.trailing.ptr }.as_ptr();
         unsafe { slice::from_raw_parts(ptr, self.len()) }
     }
 
@@ -66,19 +179,51 @@ impl TinyBoxedStr {
         unsafe { str::from_utf8_unchecked(self.as_bytes()) }
     }
 
+    /// Compares two inline strings by comparing `len_tag`, `prefix`, and `suffix` directly instead
+    /// of going through `as_str`.
+    ///
+    /// This only touches the struct's defined fields, not its raw byte representation: for some
+    /// `N` there is `#[repr(C)]` padding between `prefix` and `trailing` (see `TinyStr`'s
+    /// docs), and that padding is never initialized by `zeroed`'s struct-literal construction, so
+    /// reading it (e.g. via a whole-struct byte/word scan) would be UB. `zeroed` does guarantee
+    /// `suffix` itself is zeroed past the string's length, so comparing the three fields directly
+    /// is equivalent to a byte-for-byte comparison of the string's actual content.
+    ///
+    /// SAFETY: only meaningful when both `self` and `other` are inline, i.e.
+    /// `raw_len() <= Self::INLINE_LEN`.
+    #[inline]
+    fn eq_inline(&self, other: &Self) -> bool {
+        if self.len_tag != other.len_tag || self.prefix != other.prefix {
+            return false;
+        }
+        // SAFETY: both `self` and `other` are inline strings, so `trailing.suffix` is the active
+        // union field for both.
+        unsafe { self.trailing.suffix == other.trailing.suffix }
+    }
+
     /// Exposes the bytes as a mutable slice.
     ///
     /// When a string is short enough to be inline, this slice points to the `prefix` and `suffix`
     /// parts of the struct. Otherwise the slice wraps the pointer to the allocation.
     ///
+    /// If this string currently shares its allocation with other `TinyStr`s (`HeapTag::Shared`)
+    /// or borrows from an arena (`HeapTag::Arena`), it is first copied into a freshly owned
+    /// allocation (copy-on-write) so that the mutation is not observed by other handles and never
+    /// touches arena memory in place.
+    ///
     /// SAFETY: As such, if the string is allocated then it is the caller's responsibility to
-    /// ensure that any modifications made to `&s.as_bytes_mut[..Self::PREFIX_LEN]` are written
-    /// to `s.prefix` as well if the string is allocated.
+    /// ensure that any modifications made to `&s.as_bytes_mut[..N]` are written to `s.prefix` as
+    /// well if the string is allocated.
     ///
     /// SAFETY: It is also the caller's responsibility to ensure that edits to the bytes do not
     /// make the bytes invalid UTF-8.
     unsafe fn as_bytes_mut(&mut self) -> &mut [u8] {
-        let ptr = if self.len <= Self::INLINE_LEN {
+        if self.raw_len() > Self::INLINE_LEN
+            && matches!(self.heap_tag(), HeapTag::Shared | HeapTag::Arena)
+        {
+            self.make_unique();
+        }
+        let ptr = if self.raw_len() <= Self::INLINE_LEN {
             let ptr = ptr::from_mut(self);
             unsafe { ptr::addr_of_mut!((*ptr).prefix) }.cast()
         } else {
@@ -87,20 +232,103 @@ impl TinyBoxedStr {
         unsafe { slice::from_raw_parts_mut(ptr, self.len()) }
     }
 
+    /// Copies a `Shared` or `Arena` string's bytes into a freshly owned `Boxed` allocation and
+    /// releases this string's reference to the old allocation (or arena).
+    ///
+    /// SAFETY: only call when `self.raw_len() > Self::INLINE_LEN` and `self.heap_tag()` is
+    /// `Shared` or `Arena`.
+    fn make_unique(&mut self) {
+        let len = self.raw_len();
+        let layout = Self::layout(len);
+        let nullable = unsafe { alloc::alloc(layout) };
+        let Some(new_ptr) = NonNull::new(nullable) else {
+            alloc::handle_alloc_error(layout);
+        };
+        let old_ptr = unsafe { self.trailing.ptr };
+        match self.heap_tag() {
+            HeapTag::Shared => {
+                unsafe {
+                    ptr::copy_nonoverlapping(old_ptr.as_ptr(), new_ptr.as_ptr(), len as usize)
+                };
+                unsafe { Self::release_shared(*old_ptr, len) };
+            }
+            HeapTag::Arena => {
+                let view = unsafe { &*old_ptr.as_ptr().cast::<ArenaView>() };
+                let src = view.arena[view.offset..view.offset + len as usize].as_ptr();
+                unsafe { ptr::copy_nonoverlapping(src, new_ptr.as_ptr(), len as usize) };
+                unsafe { Self::release_arena(*old_ptr) };
+            }
+            HeapTag::Boxed => unreachable!("make_unique is only called for Shared/Arena strings"),
+        }
+        self.trailing = TinyStrTrailing {
+            ptr: ManuallyDrop::new(new_ptr),
+        };
+        self.len_tag = Self::make_len_tag(len, HeapTag::Boxed);
+    }
+
+    /// Returns a pointer to the `SharedHeader` that precedes `data`'s bytes.
+    ///
+    /// SAFETY: `data` must point at the bytes of a `Shared` string's allocation.
+    unsafe fn shared_header(data: NonNull<u8>) -> *mut SharedHeader {
+        unsafe { data.as_ptr().sub(SharedHeader::SIZE) }.cast()
+    }
+
+    /// The layout of a `Shared` allocation of `len` bytes, and the offset of the data (the
+    /// string's bytes) from the start of the allocation.
+    ///
+    /// The offset is always `SharedHeader::SIZE` since `[u8]` has an alignment of one, but it is
+    /// computed via `Layout::extend` to stay correct if `SharedHeader` ever gains other fields.
+    fn shared_layout(len: u8) -> (alloc::Layout, usize) {
+        let header = alloc::Layout::new::<SharedHeader>();
+        let data = alloc::Layout::array::<u8>(len as usize).expect("a valid layout for an array");
+        let (layout, offset) = header.extend(data).expect("a valid combined layout");
+        (layout.pad_to_align(), offset)
+    }
+
+    /// Releases one reference to a `Shared` allocation of `len` bytes, deallocating it if this was
+    /// the last reference.
+    ///
+    /// SAFETY: `data` must point at the bytes of a `Shared` string's allocation of `len` bytes, and
+    /// must not be used again afterwards.
+    unsafe fn release_shared(data: NonNull<u8>, len: u8) {
+        let header = unsafe { Self::shared_header(data) };
+        // `Release` ensures writes from other threads that happened before their `drop`/`clone`
+        // are visible here once we observe the count hitting zero; the `Acquire` fence below
+        // pairs with it to make sure we only deallocate after every other reference is gone.
+        if unsafe { &*header }
+            .count
+            .fetch_sub(1, atomic::Ordering::Release)
+            == 1
+        {
+            atomic::fence(atomic::Ordering::Acquire);
+            let (layout, _offset) = Self::shared_layout(len);
+            unsafe { alloc::dealloc(header.cast::<u8>(), layout) };
+        }
+    }
+
+    /// Releases an `Arena` string's `ArenaView`, dropping its `Arc<[u8]>` handle (deallocating the
+    /// arena once every view into it is gone) and freeing the small `ArenaView` allocation itself.
+    ///
+    /// SAFETY: `ptr` must have been produced by leaking a `Box<ArenaView>` (as
+    /// `Self::from_arena_unchecked` and `clone` do), and must not be used again afterwards.
+    unsafe fn release_arena(ptr: NonNull<u8>) {
+        drop(unsafe { Box::from_raw(ptr.as_ptr().cast::<ArenaView>()) });
+    }
+
     fn layout(len: u8) -> alloc::Layout {
         alloc::Layout::array::<u8>(len as usize)
             .expect("a valid layout for an array")
             .pad_to_align()
     }
 
-    /// Creates a new `TinyBoxedStr` of the given length with all bytes zeroed.
+    /// Creates a new `TinyStr` of the given length with all bytes zeroed.
     ///
     /// While this is used to create uninitialized strings which are later filled, note that the
     /// zero byte is valid UTF-8 so the zeroed representation is always valid.
     fn zeroed(len: u8) -> Self {
         let trailing = if len <= Self::INLINE_LEN {
-            TinyBoxedStrTrailing {
-                suffix: [0; Self::SUFFIX_LEN],
+            TinyStrTrailing {
+                suffix: [0; SUFFIX_LEN],
             }
         } else {
             let layout = Self::layout(len);
@@ -108,30 +336,213 @@ impl TinyBoxedStr {
             let Some(ptr) = NonNull::new(nullable) else {
                 alloc::handle_alloc_error(layout);
             };
-            TinyBoxedStrTrailing {
+            TinyStrTrailing {
                 ptr: ManuallyDrop::new(ptr),
             }
         };
         Self {
-            len,
-            prefix: [0; Self::PREFIX_LEN],
+            len_tag: Self::make_len_tag(len, HeapTag::Boxed),
+            prefix: [0; N],
             trailing,
         }
     }
+
+    /// Turns this string into a cheap-to-clone `Shared` handle, allocating a fresh
+    /// header-prefixed allocation if it is not already `Shared`.
+    ///
+    /// Subsequent calls to `clone` on the result (and on this string) become an `O(1)` refcount
+    /// bump instead of a byte copy. This is useful for syntax tokens and theme scope names that
+    /// end up duplicated across many spans.
+    pub fn into_shared(self) -> Self {
+        let len = self.raw_len();
+        if len <= Self::INLINE_LEN {
+            return self;
+        }
+        if self.heap_tag() == HeapTag::Shared {
+            return self;
+        }
+
+        let (layout, offset) = Self::shared_layout(len);
+        let nullable = unsafe { alloc::alloc(layout) };
+        let Some(alloc_ptr) = NonNull::new(nullable) else {
+            alloc::handle_alloc_error(layout);
+        };
+        unsafe {
+            alloc_ptr.cast::<SharedHeader>().write(SharedHeader {
+                count: AtomicUsize::new(1),
+            })
+        };
+        let data_ptr = unsafe { NonNull::new_unchecked(alloc_ptr.as_ptr().add(offset)) };
+
+        let this = ManuallyDrop::new(self);
+        let heap_tag = this.heap_tag();
+        // The source bytes live in different places depending on `heap_tag`: a direct pointer for
+        // `Boxed`, or behind an `ArenaView` for `Arena`. Always go through `as_bytes` so this
+        // stays correct regardless of representation, instead of assuming a `Boxed` layout.
+        unsafe {
+            ptr::copy_nonoverlapping(this.as_bytes().as_ptr(), data_ptr.as_ptr(), len as usize)
+        };
+        let old_ptr = unsafe { this.trailing.ptr };
+        match heap_tag {
+            HeapTag::Boxed => unsafe { alloc::dealloc(old_ptr.as_ptr(), Self::layout(len)) },
+            HeapTag::Shared => unreachable!("already returned above for Shared strings"),
+            HeapTag::Arena => unsafe { Self::release_arena(*old_ptr) },
+        }
+
+        Self {
+            len_tag: Self::make_len_tag(len, HeapTag::Shared),
+            prefix: this.prefix,
+            trailing: TinyStrTrailing {
+                ptr: ManuallyDrop::new(data_ptr),
+            },
+        }
+    }
+
+    /// Creates a view of `arena[offset..offset + len]` without copying its bytes, holding a
+    /// reference to `arena` so that it outlives this string.
+    ///
+    /// Strings short enough to be inlined are copied out of the arena immediately (there is no
+    /// point borrowing for a handful of bytes); only strings longer than the inline threshold
+    /// actually keep an `Arc` to `arena` alive.
+    pub fn from_arena(arena: Arc<[u8]>, offset: usize, len: usize) -> Result<Self, ArenaError> {
+        if len > Self::MAX_LEN {
+            return Err(ArenaError::TooLong);
+        }
+        let end = offset.checked_add(len).ok_or(ArenaError::OutOfBounds)?;
+        let bytes = arena.get(offset..end).ok_or(ArenaError::OutOfBounds)?;
+        str::from_utf8(bytes).map_err(ArenaError::InvalidUtf8)?;
+        Ok(Self::from_arena_unchecked(arena, offset, len as u8))
+    }
+
+    /// SAFETY: `arena[offset..offset + len as usize]` must be in bounds and valid UTF-8, and
+    /// `len` must be at most `Self::MAX_LEN`.
+    fn from_arena_unchecked(arena: Arc<[u8]>, offset: usize, len: u8) -> Self {
+        let bytes = &arena[offset..offset + len as usize];
+        if len <= Self::INLINE_LEN {
+            let mut this = Self::zeroed(len);
+            // SAFETY: the caller guarantees `bytes` is valid UTF-8.
+            unsafe { this.as_bytes_mut() }.copy_from_slice(bytes);
+            return this;
+        }
+
+        let mut prefix = [0; N];
+        prefix.copy_from_slice(&bytes[..N]);
+        let view = Box::new(ArenaView { arena, offset });
+        let ptr = NonNull::from(Box::leak(view)).cast();
+
+        Self {
+            len_tag: Self::make_len_tag(len, HeapTag::Arena),
+            prefix,
+            trailing: TinyStrTrailing {
+                ptr: ManuallyDrop::new(ptr),
+            },
+        }
+    }
+
+    /// Parses a contiguous buffer of `(varint length, UTF-8 bytes)` records into views over a
+    /// single shared arena, without giving every non-inline string its own allocation.
+    ///
+    /// This is meant for loading large on-disk tables (snippet dictionaries, precomputed theme
+    /// palettes) in one shot: `buf` is copied into one `Arc<[u8]>` arena up front, each record's
+    /// UTF-8 is validated exactly once here (not on every later access), and strings longer than
+    /// the inline threshold hand out views into the arena instead of being copied again.
+    pub fn parse_many(buf: &[u8]) -> Result<Vec<Self>, ArenaParseError> {
+        let arena: Arc<[u8]> = Arc::from(buf);
+        let mut strings = Vec::new();
+        let mut pos = 0;
+        while pos < arena.len() {
+            let (len, header_len) =
+                read_varint(&arena[pos..]).ok_or(ArenaParseError::Truncated)?;
+            pos += header_len;
+            let len = usize::try_from(len).map_err(|_| ArenaParseError::TooLong)?;
+            if len > Self::MAX_LEN {
+                return Err(ArenaParseError::TooLong);
+            }
+            let end = pos.checked_add(len).ok_or(ArenaParseError::Truncated)?;
+            let bytes = arena.get(pos..end).ok_or(ArenaParseError::Truncated)?;
+            str::from_utf8(bytes).map_err(ArenaParseError::InvalidUtf8)?;
+
+            strings.push(Self::from_arena_unchecked(Arc::clone(&arena), pos, len as u8));
+            pos = end;
+        }
+        Ok(strings)
+    }
+}
+
+/// Reads a single unsigned LEB128 varint from the start of `bytes`.
+///
+/// Returns the decoded value and the number of bytes it occupied, or `None` if `bytes` ends
+/// before a complete varint does or the varint runs longer than ten bytes.
+fn read_varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// An error produced by [`TinyStr::from_arena`].
+#[derive(Debug)]
+pub enum ArenaError {
+    /// The requested length was longer than `TinyStr::<N>::MAX_LEN`.
+    TooLong,
+    /// `offset..offset + len` was out of bounds of the arena.
+    OutOfBounds,
+    /// The requested bytes were not valid UTF-8.
+    InvalidUtf8(str::Utf8Error),
+}
+
+impl fmt::Display for ArenaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLong => write!(f, "string was too long to be stored as a `TinyStr`"),
+            Self::OutOfBounds => write!(f, "offset/length were out of bounds of the arena"),
+            Self::InvalidUtf8(err) => write!(f, "arena bytes were not valid UTF-8: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ArenaError {}
+
+/// An error produced by [`TinyStr::parse_many`].
+#[derive(Debug)]
+pub enum ArenaParseError {
+    /// The buffer ended partway through a varint length prefix or a record's bytes, or a length
+    /// prefix ran longer than ten bytes.
+    Truncated,
+    /// A record was longer than `TinyStr::<N>::MAX_LEN`.
+    TooLong,
+    /// A record's bytes were not valid UTF-8.
+    InvalidUtf8(str::Utf8Error),
+}
+
+impl fmt::Display for ArenaParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => f.write_str("buffer ended partway through a record"),
+            Self::TooLong => write!(f, "a record was too long to be stored as a `TinyStr`"),
+            Self::InvalidUtf8(err) => write!(f, "a record's bytes were not valid UTF-8: {err}"),
+        }
+    }
 }
 
+impl std::error::Error for ArenaParseError {}
+
 #[derive(Debug)]
 pub struct TooLongError;
 
 impl fmt::Display for TooLongError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("string was too long to be stored as a `TinyBoxedStr` (max 256 bytes)")
+        f.write_str("string was too long to be stored as a `TinyStr` (max 63 bytes)")
     }
 }
 
 impl std::error::Error for TooLongError {}
 
-impl TryFrom<&str> for TinyBoxedStr {
+impl<const N: usize> TryFrom<&str> for TinyStr<N> {
     type Error = TooLongError;
 
     fn try_from(s: &str) -> Result<Self, Self::Error> {
@@ -142,17 +553,16 @@ impl TryFrom<&str> for TinyBoxedStr {
         let mut this = Self::zeroed(s.len() as u8);
         // SAFETY: if `s` is valid UTF-8, `this`'s bytes will be valid UTF-8.
         unsafe { this.as_bytes_mut() }.copy_from_slice(s.as_bytes());
-        if this.len > Self::INLINE_LEN {
-            this.prefix
-                .copy_from_slice(&s.as_bytes()[..Self::PREFIX_LEN]);
+        if this.raw_len() > Self::INLINE_LEN {
+            this.prefix.copy_from_slice(&s.as_bytes()[..N]);
         }
         Ok(this)
     }
 }
 
-// NOTE: converting from a `String` to a `TinyBoxedStr` is cheap when the string's length is equal
-// to its capacity.
-impl TryFrom<String> for TinyBoxedStr {
+// NOTE: converting from a `String` to a `TinyStr` is cheap when the string's length is equal to
+// its capacity.
+impl<const N: usize> TryFrom<String> for TinyStr<N> {
     type Error = TooLongError;
 
     fn try_from(s: String) -> Result<Self, Self::Error> {
@@ -165,28 +575,32 @@ impl TryFrom<String> for TinyBoxedStr {
         // exactly (i.e. `s.len() == s.capacity()`). A `Box<str>` is defined as being allocated
         // exactly so we first convert to `Box<str>` (which will reallocate if the capacity is not
         // the same as the length) and then steal its pointer.
+        //
+        // This only produces a `Boxed` string: a `Shared` string needs its allocation prefixed
+        // with a `SharedHeader`, which a stolen `Box<str>` pointer does not have. Use
+        // `into_shared` afterwards if a cheap-to-clone handle is needed.
 
         if s.len() > Self::MAX_LEN {
             return Err(TooLongError);
         }
 
         let len = s.len() as u8;
-        let mut prefix = [0; Self::PREFIX_LEN];
-        prefix.copy_from_slice(&s.as_bytes()[..Self::PREFIX_LEN]);
+        let mut prefix = [0; N];
+        prefix.copy_from_slice(&s.as_bytes()[..N]);
         let ptr = Box::into_raw(s.into_boxed_str()).cast::<u8>();
         // SAFETY: `Box::into_raw` docs guarantee non-null.
         let ptr = ManuallyDrop::new(unsafe { NonNull::new_unchecked(ptr) });
-        let trailing = TinyBoxedStrTrailing { ptr };
+        let trailing = TinyStrTrailing { ptr };
 
         Ok(Self {
-            len,
+            len_tag: Self::make_len_tag(len, HeapTag::Boxed),
             prefix,
             trailing,
         })
     }
 }
 
-impl TryFrom<Cow<'_, str>> for TinyBoxedStr {
+impl<const N: usize> TryFrom<Cow<'_, str>> for TinyStr<N> {
     type Error = TooLongError;
 
     fn try_from(s: Cow<'_, str>) -> Result<Self, Self::Error> {
@@ -197,7 +611,7 @@ impl TryFrom<Cow<'_, str>> for TinyBoxedStr {
     }
 }
 
-impl TryFrom<ropey::RopeSlice<'_>> for TinyBoxedStr {
+impl<const N: usize> TryFrom<ropey::RopeSlice<'_>> for TinyStr<N> {
     type Error = TooLongError;
 
     fn try_from(slice: ropey::RopeSlice<'_>) -> Result<Self, Self::Error> {
@@ -208,84 +622,162 @@ impl TryFrom<ropey::RopeSlice<'_>> for TinyBoxedStr {
     }
 }
 
-impl Drop for TinyBoxedStr {
+impl<const N: usize> Drop for TinyStr<N> {
     fn drop(&mut self) {
-        if self.len > Self::INLINE_LEN {
-            let ptr = unsafe { self.trailing.ptr }.as_ptr();
-            let layout = Self::layout(self.len);
-            unsafe { alloc::dealloc(ptr, layout) }
+        let len = self.raw_len();
+        if len > Self::INLINE_LEN {
+            let ptr = unsafe { self.trailing.ptr };
+            match self.heap_tag() {
+                HeapTag::Boxed => {
+                    let layout = Self::layout(len);
+                    unsafe { alloc::dealloc(ptr.as_ptr(), layout) }
+                }
+                HeapTag::Shared => unsafe { Self::release_shared(*ptr, len) },
+                HeapTag::Arena => unsafe { Self::release_arena(*ptr) },
+            }
         }
     }
 }
 
-impl Clone for TinyBoxedStr {
+impl<const N: usize> Clone for TinyStr<N> {
     fn clone(&self) -> Self {
-        let mut this = Self::zeroed(self.len);
+        let len = self.raw_len();
+        if len > Self::INLINE_LEN {
+            match self.heap_tag() {
+                HeapTag::Shared => {
+                    // O(1): bump the shared allocation's reference count and copy the two
+                    // `usize`s.
+                    let ptr = unsafe { self.trailing.ptr };
+                    let header = unsafe { Self::shared_header(*ptr) };
+                    // `Relaxed` is sufficient: we are only incrementing a count, and new
+                    // references can only be created from one that already exists and is
+                    // therefore already synchronized with every previous access.
+                    unsafe { &*header }
+                        .count
+                        .fetch_add(1, atomic::Ordering::Relaxed);
+                    return Self {
+                        len_tag: self.len_tag,
+                        prefix: self.prefix,
+                        trailing: TinyStrTrailing { ptr },
+                    };
+                }
+                HeapTag::Arena => {
+                    // O(1): clone the `Arc` and allocate a new (small, fixed-size) `ArenaView`.
+                    let old_ptr = unsafe { self.trailing.ptr };
+                    let view = unsafe { &*old_ptr.as_ptr().cast::<ArenaView>() };
+                    let view = Box::new(ArenaView {
+                        arena: Arc::clone(&view.arena),
+                        offset: view.offset,
+                    });
+                    let ptr = NonNull::from(Box::leak(view)).cast();
+                    return Self {
+                        len_tag: self.len_tag,
+                        prefix: self.prefix,
+                        trailing: TinyStrTrailing {
+                            ptr: ManuallyDrop::new(ptr),
+                        },
+                    };
+                }
+                HeapTag::Boxed => {}
+            }
+        }
+
+        let mut this = Self::zeroed(len);
         // SAFETY: if `self` is valid UTF-8 then `this` will be too.
         unsafe { this.as_bytes_mut() }.copy_from_slice(self.as_bytes());
-        if this.len > Self::INLINE_LEN {
-            this.prefix
-                .copy_from_slice(&self.as_bytes()[..Self::PREFIX_LEN]);
+        if this.raw_len() > Self::INLINE_LEN {
+            this.prefix.copy_from_slice(&self.as_bytes()[..N]);
         }
         this
     }
 }
 
-impl Default for TinyBoxedStr {
+impl<const N: usize> Default for TinyStr<N> {
     fn default() -> Self {
         Self::zeroed(0)
     }
 }
 
-impl AsRef<str> for TinyBoxedStr {
+impl<const N: usize> AsRef<str> for TinyStr<N> {
     fn as_ref(&self) -> &str {
         self.as_str()
     }
 }
 
-impl Borrow<str> for TinyBoxedStr {
+impl<const N: usize> Borrow<str> for TinyStr<N> {
     fn borrow(&self) -> &str {
         self.as_str()
     }
 }
 
-// NOTE: this could be specialized to optimize the number of comparison operations. We could cast
-// the first `usize` of memory together to do a single comparison (and same for the suffixes).
-// This optimization would only matter if we compared these strings very frequently however.
-impl PartialEq for TinyBoxedStr {
+impl<const N: usize> PartialEq for TinyStr<N> {
     fn eq(&self, other: &Self) -> bool {
+        if self.raw_len() <= Self::INLINE_LEN && other.raw_len() <= Self::INLINE_LEN {
+            return self.eq_inline(other);
+        }
         self.as_str() == other.as_str()
     }
 }
 
-impl Eq for TinyBoxedStr {}
+impl<const N: usize> Eq for TinyStr<N> {}
 
-impl PartialEq<str> for TinyBoxedStr {
+impl<const N: usize> PartialEq<str> for TinyStr<N> {
     fn eq(&self, other: &str) -> bool {
         self.as_str() == other
     }
 }
 
-impl hash::Hash for TinyBoxedStr {
+impl<const N: usize> PartialOrd for TinyStr<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for TinyStr<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // The `prefix` bytes mirror the start of every string's content (inline or heap, see the
+        // `prefix`-cache invariant), zero-padded past the string's own length. Whenever the
+        // prefixes differ, the real (non-padded) bytes they were copied from must differ at the
+        // same position, so the prefix comparison alone already gives the right answer; only a
+        // matching prefix needs the full (and possibly heap-touching) comparison.
+        match self.prefix.cmp(&other.prefix) {
+            Ordering::Equal => self.as_str().cmp(other.as_str()),
+            ord => ord,
+        }
+    }
+}
+
+impl<const N: usize> PartialOrd<str> for TinyStr<N> {
+    fn partial_cmp(&self, other: &str) -> Option<Ordering> {
+        self.as_str().partial_cmp(other)
+    }
+}
+
+impl<const N: usize> hash::Hash for TinyStr<N> {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
         self.as_str().hash(state)
     }
 }
 
-impl fmt::Debug for TinyBoxedStr {
+impl<const N: usize> fmt::Debug for TinyStr<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.as_str().fmt(f)
     }
 }
 
-impl fmt::Display for TinyBoxedStr {
+impl<const N: usize> fmt::Display for TinyStr<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.as_str().fmt(f)
     }
 }
 
-unsafe impl Send for TinyBoxedStr {}
-unsafe impl Sync for TinyBoxedStr {}
+unsafe impl<const N: usize> Send for TinyStr<N> {}
+unsafe impl<const N: usize> Sync for TinyStr<N> {}
+
+// `len_tag` being a `NonZeroU8` is supposed to give `TinyStr<N>` a niche that `Option` can use
+// for its `None` variant; check that this keeps holding for the default `TinyBoxedStr`.
+const _: () = assert!(size_of::<Option<TinyBoxedStr>>() == size_of::<TinyBoxedStr>());
+
 /// Concatenates strings together.
 ///
 /// `str_concat!(a, " ", b, " ", c)` is:
@@ -304,3 +796,76 @@ macro_rules! str_concat {
         buf
     }}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every byte length up to `MAX_LEN`, crossing the inline/heap boundary at `INLINE_LEN`.
+    fn lengths() -> impl Iterator<Item = usize> {
+        0..=TinyBoxedStr::MAX_LEN
+    }
+
+    fn string_of_len(len: usize) -> String {
+        // Cycle through sixteen distinct ASCII letters so the prefix/suffix split is exercised
+        // with varied content, not just runs of the same byte.
+        "abcdefghijklmnop".chars().cycle().take(len).collect()
+    }
+
+    #[test]
+    fn word_wise_eq_agrees_with_str_eq() {
+        for len in lengths() {
+            let s = string_of_len(len);
+            let a: TinyBoxedStr = s.as_str().try_into().unwrap();
+            let b: TinyBoxedStr = s.as_str().try_into().unwrap();
+            assert_eq!(a, b, "length {len}");
+            assert_eq!(a.as_str() == b.as_str(), a == b, "length {len}");
+        }
+    }
+
+    #[test]
+    fn word_wise_eq_detects_differences_at_every_position() {
+        for len in lengths().filter(|&len| len > 0) {
+            let s = string_of_len(len);
+            for i in 0..len {
+                let mut bytes = s.clone().into_bytes();
+                // Flip a bit that keeps the byte within ASCII, so the string stays valid UTF-8.
+                bytes[i] ^= 0x01;
+                let different = String::from_utf8(bytes).unwrap();
+
+                let a: TinyBoxedStr = s.as_str().try_into().unwrap();
+                let b: TinyBoxedStr = different.as_str().try_into().unwrap();
+                assert_eq!(a.as_str() == b.as_str(), a == b, "length {len}, position {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn ord_agrees_with_str_ord() {
+        let words = [
+            "", "a", "ab", "b", "ba", "abc", "abd",
+            &string_of_len(TinyBoxedStr::INLINE_LEN as usize),
+            &string_of_len(TinyBoxedStr::INLINE_LEN as usize + 1),
+            &string_of_len(TinyBoxedStr::MAX_LEN),
+        ];
+        for a in words {
+            for b in words {
+                let ta: TinyBoxedStr = a.try_into().unwrap();
+                let tb: TinyBoxedStr = b.try_into().unwrap();
+                assert_eq!(ta.cmp(&tb), a.cmp(b), "{a:?} vs {b:?}");
+                assert_eq!(
+                    ta.partial_cmp(&tb),
+                    a.partial_cmp(b),
+                    "{a:?} vs {b:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn partial_eq_and_ord_with_str() {
+        let s: TinyBoxedStr = "hello".try_into().unwrap();
+        assert_eq!(s, *"hello");
+        assert_eq!(s.partial_cmp("hellp"), "hello".partial_cmp("hellp"));
+    }
+}